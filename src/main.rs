@@ -1,11 +1,29 @@
+use std::collections::HashSet;
 use std::process::ExitCode;
 use std::path::{Path, PathBuf};
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read as _, Write};
+use std::sync::mpsc;
+use std::time::{Duration, UNIX_EPOCH};
 use clap::{App, Arg, SubCommand};
+use ignore::{gitignore::GitignoreBuilder, WalkBuilder};
+use notify::{RecursiveMode, Watcher};
 use rusqlite::Connection;
 use rusqlite_migration::{Migrations, M};
 
+const CBIGNORE_FILENAME: &str = ".cbignore";
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Rejects the value at argument-parsing time with a normal clap usage error,
+// rather than letting a bad --max-depth/--min-depth/--prune-after-days panic
+// deep inside `run_index`.
+fn validate_number<T: std::str::FromStr>(value: String) -> Result<(), String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>().map(|_| ()).map_err(|e| e.to_string())
+}
+
 fn main() -> ExitCode {
     let matches = App::new("Codebased")
         .version("0.0.1")
@@ -24,7 +42,68 @@ fn main() -> ExitCode {
                 .long("limit")
                 .value_name("NUMBER")
                 .help("Limit the number of results")
+                .takes_value(true)
+                .validator(validate_number::<usize>))
+            .arg(Arg::with_name("session")
+                .short("s")
+                .long("session")
+                .value_name("ID")
+                .help("Session id used to track pagination for search-next")
+                .takes_value(true))
+            .arg(Arg::with_name("regex")
+                .long("regex")
+                .help("Treat the query as a regular expression and scan file bodies line by line"))
+            .arg(Arg::with_name("case-insensitive")
+                .short("i")
+                .long("case-insensitive")
+                .help("Match the regex case-insensitively (only with --regex)"))
+            .arg(Arg::with_name("word-boundary")
+                .short("w")
+                .long("word-boundary")
+                .help("Require the regex match to fall on word boundaries (only with --regex)")))
+        .subcommand(SubCommand::with_name("search-next")
+            .about("Fetch the next page of the last search")
+            .arg(Arg::with_name("session")
+                .short("s")
+                .long("session")
+                .value_name("ID")
+                .help("Session id used to track pagination for search-next")
                 .takes_value(true)))
+        .subcommand(SubCommand::with_name("index")
+            .about("Index files into the search database")
+            .arg(Arg::with_name("paths")
+                .help("Paths to index (defaults to the repository root)")
+                .required(false)
+                .multiple(true))
+            .arg(Arg::with_name("max-depth")
+                .long("max-depth")
+                .value_name("NUMBER")
+                .help("Maximum depth to descend into each path")
+                .takes_value(true)
+                .validator(validate_number::<usize>))
+            .arg(Arg::with_name("min-depth")
+                .long("min-depth")
+                .value_name("NUMBER")
+                .help("Minimum depth before files are indexed")
+                .takes_value(true)
+                .validator(validate_number::<usize>))
+            .arg(Arg::with_name("follow-symlinks")
+                .long("follow-symlinks")
+                .help("Follow symbolic links while walking"))
+            .arg(Arg::with_name("prune-after-days")
+                .long("prune-after-days")
+                .value_name("DAYS")
+                .help("Remove missing files from the index after this many days unvisited")
+                .takes_value(true)
+                .validator(validate_number::<i64>)))
+        .subcommand(SubCommand::with_name("open")
+            .about("Record that a file was opened, boosting its search ranking")
+            .arg(Arg::with_name("path")
+                .help("Path of the file that was opened, relative to the repository root")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("watch")
+            .about("Watch the repository and keep the index up to date"))
         .get_matches();
 
     match matches.subcommand() {
@@ -39,16 +118,73 @@ fn main() -> ExitCode {
             }
         }
         ("search", Some(search_matches)) => {
-            if let Some(query) = search_matches.value_of("query") {
-                println!("Searching for: {}", query);
-            } else {
+            let Some(query) = search_matches.value_of("query") else {
                 println!("Searching without a query.");
+                return ExitCode::SUCCESS;
             };
-            if let Some(limit) = search_matches.value_of("limit").map(|l| l.parse::<usize>().unwrap()) {
-                println!("Limit: {}", limit);
+            let limit = search_matches.value_of("limit")
+                .map(|l| l.parse::<usize>().unwrap())
+                .unwrap_or(DEFAULT_SEARCH_LIMIT);
+            let session = search_matches.value_of("session").unwrap_or(DEFAULT_SESSION);
+            let result = if search_matches.is_present("regex") {
+                run_regex_search(
+                    query,
+                    limit,
+                    search_matches.is_present("case-insensitive"),
+                    search_matches.is_present("word-boundary"),
+                )
             } else {
-                println!("Searching without a limit.");
+                run_search(query, limit, session)
             };
+            if let Err(e) = result {
+                eprintln!("Search failed: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        ("search-next", Some(next_matches)) => {
+            let session = next_matches.value_of("session").unwrap_or(DEFAULT_SESSION);
+            match run_search_next(session) {
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Search failed: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        ("index", Some(index_matches)) => {
+            let paths = index_matches.values_of("paths")
+                .map(|vals| vals.map(PathBuf::from).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let max_depth = index_matches.value_of("max-depth").map(|d| d.parse::<usize>().unwrap());
+            let min_depth = index_matches.value_of("min-depth").map(|d| d.parse::<usize>().unwrap());
+            let follow_symlinks = index_matches.is_present("follow-symlinks");
+            let prune_after_days = index_matches.value_of("prune-after-days")
+                .map(|d| d.parse::<i64>().unwrap())
+                .unwrap_or(DEFAULT_PRUNE_AFTER_DAYS);
+            match run_index(paths, max_depth, min_depth, follow_symlinks, prune_after_days) {
+                Ok(count) => println!("Indexed {} file(s).", count),
+                Err(e) => {
+                    eprintln!("Indexing failed: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        ("open", Some(open_matches)) => {
+            let path = open_matches.value_of("path").unwrap();
+            match run_open(path) {
+                Ok(_) => println!("Recorded open: {}", path),
+                Err(e) => {
+                    eprintln!("Failed to record open: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        ("watch", Some(_)) => {
+            println!("Watching for changes. Press Ctrl+C to stop.");
+            if let Err(e) = run_watch() {
+                eprintln!("Watch failed: {}", e);
+                return ExitCode::FAILURE;
+            }
         }
         _ => {
             println!("Please provide a valid command. Use --help for more information.");
@@ -59,10 +195,465 @@ fn main() -> ExitCode {
     ExitCode::SUCCESS
 }
 
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+const DEFAULT_SESSION: &str = "default";
+
+// Frecency-weighted bm25 score: the raw FTS5 rank is biased toward files that
+// were opened recently and often, so hot files keep surfacing near the top.
+const SCORED_CTE_SQL: &str = "WITH scored AS ( \
+    SELECT documents.path AS path, \
+           snippet(documents, 1, '[', ']', '...', 10) AS snippet, \
+           documents.rowid AS rowid, \
+           (documents.rank - COALESCE(files.rank, 0) * (CASE \
+               WHEN (?3 - COALESCE(files.last_accessed, 0)) <= 3600 THEN 4.0 \
+               WHEN (?3 - COALESCE(files.last_accessed, 0)) <= 86400 THEN 2.0 \
+               WHEN (?3 - COALESCE(files.last_accessed, 0)) <= 604800 THEN 0.5 \
+               ELSE 0.25 END)) AS score \
+    FROM documents LEFT JOIN files ON files.path = documents.path \
+    WHERE documents MATCH ?1 \
+) ";
+
+fn run_search(query: &str, limit: usize, session: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let conn = Connection::open(root.join(".codebased.db"))?;
+    // Frozen once per search and carried through search_state so every page
+    // of the same search scores against the same recency buckets.
+    let now = unix_now()?;
+
+    let sql = format!("{}SELECT path, snippet, score, rowid FROM scored ORDER BY score, rowid LIMIT ?2", SCORED_CTE_SQL);
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params![query, limit as i64, now], read_result_row)?;
+
+    print_search_page(&conn, query, limit, session, now, rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn run_search_next(session: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let conn = Connection::open(root.join(".codebased.db"))?;
+
+    let cursor: Option<(String, i64, f64, i64, i64)> = conn.query_row(
+        "SELECT query, limit_n, last_rank, last_rowid, now FROM search_state WHERE session = ?1",
+        rusqlite::params![session],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+    ).ok();
+
+    let Some((query, limit, last_score, last_rowid, now)) = cursor else {
+        println!("No previous search for this session. Run `search` first.");
+        return Ok(());
+    };
+
+    let sql = format!(
+        "{}SELECT path, snippet, score, rowid FROM scored \
+         WHERE score > ?4 OR (score = ?4 AND rowid > ?5) ORDER BY score, rowid LIMIT ?2",
+        SCORED_CTE_SQL,
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(
+        rusqlite::params![query, limit, now, last_score, last_rowid],
+        read_result_row,
+    )?;
+
+    print_search_page(&conn, &query, limit as usize, session, now, rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn read_result_row(row: &rusqlite::Row) -> rusqlite::Result<(String, String, f64, i64)> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn run_regex_search(
+    query: &str,
+    limit: usize,
+    case_insensitive: bool,
+    word_boundary: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let conn = Connection::open(root.join(".codebased.db"))?;
+
+    let pattern = if word_boundary { format!(r"\b(?:{})\b", query) } else { query.to_string() };
+    let matcher = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(case_insensitive)
+        .build()?;
+
+    // Narrow candidates with the FTS index when the pattern has a required
+    // literal substring; otherwise fall back to scanning every indexed file.
+    let candidates: Vec<(String, String)> = match extract_literal_prefilter(query) {
+        Some(literal) => {
+            let mut stmt = conn.prepare(
+                "SELECT path, body FROM documents WHERE documents MATCH ?1",
+            )?;
+            let rows = stmt.query_map(rusqlite::params![format!("\"{}\"", literal.replace('"', ""))], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?.collect::<Result<_, _>>()?;
+            rows
+        }
+        None => {
+            let mut stmt = conn.prepare("SELECT path, body FROM documents")?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<_, _>>()?;
+            rows
+        }
+    };
+
+    let mut printed = 0;
+    'files: for (path, body) in &candidates {
+        for (line_no, line) in body.lines().enumerate() {
+            if let Some(m) = matcher.find(line) {
+                println!("{}:{}:{}: {}", path, line_no + 1, m.start() + 1, line.trim());
+                printed += 1;
+                if printed >= limit {
+                    break 'files;
+                }
+            }
+        }
+    }
+    if printed == 0 {
+        println!("No matches for: {}", query);
+    }
+    Ok(())
+}
+
+// Pulls the longest run of non-metacharacter text out of a regex pattern, so
+// it can be used as a cheap FTS5 prefilter before running the full regex.
+// Bails out on `|`: a required literal only narrows the candidate set
+// correctly when the pattern is a single concatenation, and alternation
+// (`TODO|FIXME`, `(a|b)`) means no substring is actually required in every
+// match, so prefiltering on one branch would silently drop the others.
+fn extract_literal_prefilter(pattern: &str) -> Option<String> {
+    if pattern.contains('|') {
+        return None;
+    }
+    const METACHARS: &str = ".^$*+?()[]{}\\";
+    let mut best = String::new();
+    let mut current = String::new();
+    for c in pattern.chars() {
+        if METACHARS.contains(c) {
+            if current.len() > best.len() {
+                best = current.clone();
+            }
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    if current.len() > best.len() {
+        best = current;
+    }
+    if best.len() >= 3 { Some(best) } else { None }
+}
+
+fn print_search_page(
+    conn: &Connection,
+    query: &str,
+    limit: usize,
+    session: &str,
+    now: i64,
+    page: Vec<(String, String, f64, i64)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if page.is_empty() {
+        println!("No (more) results for: {}", query);
+        return Ok(());
+    }
+
+    for (path, snippet, _, _) in &page {
+        println!("{}: {}", path, snippet);
+    }
+
+    let (_, _, last_rank, last_rowid) = *page.last().unwrap();
+    conn.execute(
+        "INSERT INTO search_state (session, query, limit_n, last_rank, last_rowid, now) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+         ON CONFLICT(session) DO UPDATE SET \
+            query = excluded.query, limit_n = excluded.limit_n, \
+            last_rank = excluded.last_rank, last_rowid = excluded.last_rowid, \
+            now = excluded.now",
+        rusqlite::params![session, query, limit as i64, last_rank, last_rowid, now],
+    )?;
+    Ok(())
+}
+
 fn run_init() -> Result<(), Box<dyn std::error::Error>> {
     let root = find_git_root()?;
     create_cbignore(&root)?;
     create_database(&root)?;
+    run_index(Vec::new(), None, None, false, DEFAULT_PRUNE_AFTER_DAYS)?;
+    Ok(())
+}
+
+const DEFAULT_PRUNE_AFTER_DAYS: i64 = 90;
+const FRECENCY_RANK_CAP: f64 = 1000.0;
+
+fn unix_now() -> Result<i64, Box<dyn std::error::Error>> {
+    Ok(std::time::SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)
+}
+
+fn run_open(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let conn = Connection::open(root.join(".codebased.db"))?;
+    record_access(&conn, path)
+}
+
+fn record_access(conn: &Connection, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let now = unix_now()?;
+    let updated = conn.execute(
+        "UPDATE files SET rank = rank + 1, last_accessed = ?2 WHERE path = ?1",
+        rusqlite::params![path, now],
+    )?;
+    if updated == 0 {
+        return Err(format!("file not indexed: {}", path).into());
+    }
+    maybe_renormalize(conn)
+}
+
+fn maybe_renormalize(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let total: f64 = conn.query_row("SELECT COALESCE(SUM(rank), 0) FROM files", [], |row| row.get(0))?;
+    if total > FRECENCY_RANK_CAP {
+        conn.execute("UPDATE files SET rank = rank / 2.0", [])?;
+    }
+    Ok(())
+}
+
+fn run_index(
+    paths: Vec<PathBuf>,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    follow_symlinks: bool,
+    prune_after_days: i64,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let mut conn = Connection::open(root.join(".codebased.db"))?;
+    let roots = if paths.is_empty() { vec![root.clone()] } else { paths };
+
+    let tx = conn.transaction()?;
+    let mut indexed = 0;
+
+    for start in &roots {
+        let mut builder = WalkBuilder::new(start);
+        builder
+            .follow_links(follow_symlinks)
+            .add_custom_ignore_filename(CBIGNORE_FILENAME);
+        if let Some(depth) = max_depth {
+            builder.max_depth(Some(depth));
+        }
+
+        for entry in builder.build() {
+            // A single unreadable or racily-deleted file shouldn't discard the
+            // whole walk's transaction; log it and keep going.
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("Skipping entry: {}", e);
+                    continue;
+                }
+            };
+            if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+                continue;
+            }
+            let path = entry.path();
+            if let Some(depth) = min_depth {
+                if entry.depth() < depth {
+                    continue;
+                }
+            }
+
+            match index_file(&tx, &root, path) {
+                Ok(true) => indexed += 1,
+                Ok(false) => {}
+                Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    {
+        let now = unix_now()?;
+        let prune_after_secs = prune_after_days * 24 * 60 * 60;
+        let mut stale = tx.prepare("SELECT path, last_accessed FROM files")?;
+        let stale_rows: Vec<(String, i64)> = stale
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (relative, last_accessed) in stale_rows {
+            if root.join(&relative).exists() {
+                continue;
+            }
+            if now - last_accessed < prune_after_secs {
+                continue;
+            }
+            remove_file_entry(&tx, &relative)?;
+        }
+    }
+
+    tx.commit()?;
+    maybe_renormalize(&conn)?;
+    Ok(indexed)
+}
+
+/// Indexes (or re-indexes, if stale) a single file on disk. Returns `true` if
+/// the FTS entry was written, `false` if the file was unchanged or binary.
+fn index_file(conn: &Connection, root: &Path, path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let relative = relative.to_string_lossy().into_owned();
+    let metadata = std::fs::metadata(path)?;
+    let size = metadata.len() as i64;
+    let mtime = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+    let existing: Option<(i64, i64)> = conn.query_row(
+        "SELECT size, mtime FROM files WHERE path = ?1",
+        rusqlite::params![relative],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).ok();
+    if existing == Some((size, mtime)) {
+        return Ok(false);
+    }
+
+    let mut contents = Vec::new();
+    File::open(path)?.read_to_end(&mut contents)?;
+    if contents.contains(&0) {
+        return Ok(false);
+    }
+    let body = String::from_utf8_lossy(&contents);
+
+    conn.execute(
+        "INSERT INTO files (path, size, mtime) VALUES (?1, ?2, ?3) \
+         ON CONFLICT(path) DO UPDATE SET size = excluded.size, mtime = excluded.mtime",
+        rusqlite::params![relative, size, mtime],
+    )?;
+    conn.execute("DELETE FROM documents WHERE path = ?1", rusqlite::params![relative])?;
+    conn.execute(
+        "INSERT INTO documents (path, body) VALUES (?1, ?2)",
+        rusqlite::params![relative, body],
+    )?;
+    Ok(true)
+}
+
+fn run_watch() -> Result<(), Box<dyn std::error::Error>> {
+    let root = find_git_root()?;
+    let conn = Connection::open(root.join(".codebased.db"))?;
+    let mut ignores = build_ignore_matcher(&root)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+
+    loop {
+        // Block for the first event, then drain whatever else arrives within
+        // the debounce window so a burst of saves collapses into one update.
+        let first = match rx.recv() {
+            Ok(event) => event,
+            Err(_) => return Ok(()),
+        };
+        let mut changed: HashSet<PathBuf> = HashSet::new();
+        collect_event_paths(first, &mut changed);
+        while let Ok(event) = rx.recv_timeout(WATCH_DEBOUNCE) {
+            collect_event_paths(event, &mut changed);
+        }
+
+        // A new directory (possibly carrying its own .gitignore/.cbignore) or
+        // a direct edit to an ignore file means the one-time snapshot taken
+        // at startup is stale; rebuild it before filtering this batch so new
+        // rules apply immediately instead of only on the next `watch` run.
+        let ignores_stale = changed.iter().any(|path| {
+            path.is_dir()
+                || path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name == ".gitignore" || name == CBIGNORE_FILENAME)
+                    .unwrap_or(false)
+        });
+        if ignores_stale {
+            match build_ignore_matcher(&root) {
+                Ok(rebuilt) => ignores = rebuilt,
+                Err(e) => eprintln!("Failed to refresh ignore rules: {}", e),
+            }
+        }
+
+        let mut updated = 0;
+        for path in changed {
+            if path.starts_with(root.join(".git")) || is_codebased_db_file(&root, &path) {
+                continue;
+            }
+            if ignores.matched(&path, path.is_dir()).is_ignore() {
+                continue;
+            }
+            if path.is_dir() {
+                continue;
+            }
+            // A save-by-rename shows up as a remove of the temp file and a
+            // create of the real one; re-checking existence (rather than
+            // reacting to the event kind) means the final rename always wins.
+            // Individual files are allowed to fail (e.g. a racing delete) without
+            // bringing down the whole watcher.
+            let outcome = if path.exists() {
+                index_file(&conn, &root, &path)
+            } else {
+                let relative = path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().into_owned();
+                remove_file_entry(&conn, &relative).map(|_| true)
+            };
+            match outcome {
+                Ok(true) => updated += 1,
+                Ok(false) => {}
+                Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        if updated > 0 {
+            maybe_renormalize(&conn)?;
+            println!("Re-indexed {} file(s).", updated);
+        }
+    }
+}
+
+fn is_codebased_db_file(root: &Path, path: &Path) -> bool {
+    path.parent() == Some(root)
+        && path.file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with(".codebased.db"))
+            .unwrap_or(false)
+}
+
+fn collect_event_paths(event: notify::Result<notify::Event>, out: &mut HashSet<PathBuf>) {
+    if let Ok(event) = event {
+        out.extend(event.paths);
+    }
+}
+
+// `cb index` honors nested .gitignore/.cbignore files via WalkBuilder, so the
+// live watcher has to collect the same set up front to avoid drifting from it.
+fn build_ignore_matcher(root: &Path) -> Result<ignore::gitignore::Gitignore, Box<dyn std::error::Error>> {
+    let mut builder = GitignoreBuilder::new(root);
+    for dir in collect_ignore_dirs(root) {
+        builder.add(dir.join(".gitignore"));
+        builder.add(dir.join(CBIGNORE_FILENAME));
+    }
+    Ok(builder.build()?)
+}
+
+// Walks with the same `WalkBuilder` the indexer uses, so this already prunes
+// ignored trees (no descending into `target/`, `node_modules/`, ...) and
+// tolerates per-entry errors instead of aborting the whole watch on one
+// unreadable or racily-deleted directory.
+fn collect_ignore_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    let walker = WalkBuilder::new(root)
+        .add_custom_ignore_filename(CBIGNORE_FILENAME)
+        .build();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Skipping entry: {}", e);
+                continue;
+            }
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            dirs.push(entry.path().to_path_buf());
+        }
+    }
+    dirs
+}
+
+fn remove_file_entry(conn: &Connection, relative: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("DELETE FROM files WHERE path = ?1", rusqlite::params![relative])?;
+    conn.execute("DELETE FROM documents WHERE path = ?1", rusqlite::params![relative])?;
     Ok(())
 }
 
@@ -91,6 +682,9 @@ fn create_database(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
     // Define migrations
     let migrations = Migrations::new(vec![
         M::up(include_str!("migrations/000_core.sql")),
+        M::up(include_str!("migrations/001_search_state.sql")),
+        M::up(include_str!("migrations/002_frecency.sql")),
+        M::up(include_str!("migrations/003_search_state_now.sql")),
     ]);
     // Apply PRAGMA
     conn.pragma_update(None, "journal_mode", &"WAL")?;
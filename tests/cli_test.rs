@@ -1,9 +1,30 @@
+use std::fs;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+
 use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// Each test gets its own repo root (a tempdir with a bare `.git` marker) so
+/// `find_git_root` never walks up into this checkout and `.codebased.db`
+/// never leaks outside the sandbox.
+fn sandbox() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    dir
+}
+
+fn cb(dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("codebased").unwrap();
+    cmd.current_dir(dir.path());
+    cmd
+}
 
 #[test]
 fn test_init_command() {
-    let mut cmd = Command::cargo_bin("codebased").unwrap();
-    cmd.arg("init")
+    let dir = sandbox();
+    cb(&dir)
+        .arg("init")
         .assert()
         .success()
         .stdout(predicates::str::contains("Initializing..."))
@@ -12,30 +33,203 @@ fn test_init_command() {
 
 #[test]
 fn test_search_command_with_query() {
-    let mut cmd = Command::cargo_bin("codebased").unwrap();
-    cmd.args(&["search", "test query"])
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+
+    cb(&dir)
+        .args(&["search", "test query"])
         .assert()
         .success()
-        .stdout(predicates::str::contains("Searching for: test query"));
+        .stdout(predicates::str::contains("No (more) results for: test query"));
 }
 
 #[test]
-fn test_search_command_with_limit() {
-    let mut cmd = Command::cargo_bin("codebased").unwrap();
-    cmd.args(&["search", "--limit", "10"])
+fn test_search_command_without_query() {
+    let dir = sandbox();
+    cb(&dir)
+        .arg("search")
         .assert()
         .success()
-        .stdout(predicates::str::contains("Limit: 10"));
+        .stdout(predicates::str::contains("Searching without a query."));
 }
 
 #[test]
 fn test_invalid_command() {
-    let mut cmd = Command::cargo_bin("codebased").unwrap();
-    cmd.arg("invalid_command")
+    let dir = sandbox();
+    cb(&dir)
+        .arg("invalid_command")
         .assert()
         .failure()
         .stderr(predicates::str::contains("error: Found argument 'invalid_command' which wasn't expected, or isn't valid in this context"))
         .stderr(predicates::str::contains("USAGE:"))
         .stderr(predicates::str::contains("codebased [SUBCOMMAND]"))
         .stderr(predicates::str::contains("For more information try --help"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_index_is_incremental() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+    fs::write(dir.path().join("tracked.txt"), "hello from tracked.txt\n").unwrap();
+
+    cb(&dir)
+        .arg("index")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Indexed 1 file(s)."));
+
+    // Nothing changed on disk, so the second pass should re-tokenize nothing.
+    cb(&dir)
+        .arg("index")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Indexed 0 file(s)."));
+}
+
+#[test]
+fn test_search_next_paginates_results() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+    fs::write(dir.path().join("f1.txt"), "uniqueterm one\n").unwrap();
+    fs::write(dir.path().join("f2.txt"), "uniqueterm two\n").unwrap();
+    fs::write(dir.path().join("f3.txt"), "uniqueterm three\n").unwrap();
+    cb(&dir).args(&["index", "f1.txt", "f2.txt", "f3.txt"]).assert().success();
+
+    let page1 = cb(&dir).args(&["search", "uniqueterm", "--limit", "1"]).output().unwrap();
+    let page1 = String::from_utf8_lossy(&page1.stdout).into_owned();
+    assert!(page1.contains("f1.txt"), "page1 was: {}", page1);
+
+    let page2 = cb(&dir).arg("search-next").output().unwrap();
+    let page2 = String::from_utf8_lossy(&page2.stdout).into_owned();
+    assert!(page2.contains("f2.txt"), "page2 was: {}", page2);
+    assert!(!page2.contains("f1.txt"), "page2 was: {}", page2);
+
+    let page3 = cb(&dir).arg("search-next").output().unwrap();
+    let page3 = String::from_utf8_lossy(&page3.stdout).into_owned();
+    assert!(page3.contains("f3.txt"), "page3 was: {}", page3);
+
+    cb(&dir)
+        .arg("search-next")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("No (more) results for: uniqueterm"));
+}
+
+#[test]
+fn test_search_next_without_prior_search() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+
+    cb(&dir)
+        .arg("search-next")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("No previous search for this session. Run `search` first."));
+}
+
+#[test]
+fn test_open_boosts_frecency_ranking() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+    fs::write(dir.path().join("a.txt"), "shared frecency content\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "shared frecency content\n").unwrap();
+    // Index explicitly in order so a.txt's rowid sorts before b.txt's, giving
+    // a deterministic baseline before either file has been opened.
+    cb(&dir).args(&["index", "a.txt", "b.txt"]).assert().success();
+
+    cb(&dir).args(&["open", "b.txt"]).assert().success();
+
+    let out = cb(&dir).args(&["search", "frecency"]).output().unwrap();
+    let out = String::from_utf8_lossy(&out.stdout).into_owned();
+    let pos_a = out.find("a.txt").expect("a.txt should appear in results");
+    let pos_b = out.find("b.txt").expect("b.txt should appear in results");
+    assert!(pos_b < pos_a, "opened file should rank first, got: {}", out);
+}
+
+#[test]
+fn test_open_unknown_path_fails() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+
+    cb(&dir)
+        .args(&["open", "does-not-exist.txt"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("file not indexed: does-not-exist.txt"));
+}
+
+#[test]
+fn test_regex_search_reports_line_and_column() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+    fs::write(dir.path().join("lib.rs"), "fn hello_world() {}\n").unwrap();
+    cb(&dir).args(&["index", "lib.rs"]).assert().success();
+
+    cb(&dir)
+        .args(&["search", r"hello_\w+", "--regex"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("lib.rs:1:4: fn hello_world() {}"));
+}
+
+#[test]
+fn test_watch_picks_up_new_files() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+
+    let mut watcher = StdCommand::new(assert_cmd::cargo::cargo_bin("codebased"))
+        .arg("watch")
+        .current_dir(dir.path())
+        .spawn()
+        .unwrap();
+
+    // Give the watcher time to start, then let a debounce window elapse after
+    // the write so the change is picked up in one batch.
+    std::thread::sleep(Duration::from_millis(500));
+    fs::write(dir.path().join("live.txt"), "watched uniquewatchterm content\n").unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+    watcher.kill().ok();
+    watcher.wait().ok();
+
+    cb(&dir)
+        .args(&["search", "uniquewatchterm"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("live.txt"));
+}
+
+#[test]
+fn test_watch_picks_up_nested_ignore_added_after_start() {
+    let dir = sandbox();
+    cb(&dir).arg("init").assert().success();
+
+    let mut watcher = StdCommand::new(assert_cmd::cargo::cargo_bin("codebased"))
+        .arg("watch")
+        .current_dir(dir.path())
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(500));
+    // Created after the watcher already started, so the ignore rule did not
+    // exist in the one-time snapshot taken at `watch` startup.
+    fs::create_dir(dir.path().join("vendor")).unwrap();
+    fs::write(dir.path().join("vendor/.gitignore"), "ignoreme.txt\n").unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    fs::write(dir.path().join("vendor/ignoreme.txt"), "uniqueignoredterm\n").unwrap();
+    fs::write(dir.path().join("vendor/tracked.txt"), "uniquewatchedterm\n").unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+    watcher.kill().ok();
+    watcher.wait().ok();
+
+    cb(&dir)
+        .args(&["search", "uniquewatchedterm"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("tracked.txt"));
+
+    cb(&dir)
+        .args(&["search", "uniqueignoredterm"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("No (more) results for: uniqueignoredterm"));
+}